@@ -1,9 +1,10 @@
-use actix_web::{web, App, HttpResponse, HttpServer, Responder};
+use actix_web::{http::header::ACCEPT, web, App, HttpRequest, HttpResponse, HttpServer, Responder};
 use clap::Parser;
+use geo::algorithm::area::Area;
 use geo::algorithm::bounding_rect::BoundingRect;
-use geo::{Contains, Point, Polygon};
+use geo::{Contains, EuclideanDistance, LineString, Point, Polygon};
 use rstar::{PointDistance, RTree, RTreeObject, AABB};
-use serde_json::Value;
+use serde_json::{json, Value};
 use shapefile::{PolygonRing, Shape};
 use std::sync::Arc;
 
@@ -18,6 +19,12 @@ mod record_to_json;
 struct Args {
     #[arg(short, long)]
     file: Option<String>,
+
+    /// 入力ファイルの形式（"shapefile" / "osm-overpass" / "geojsonl"）。
+    /// 省略した場合は拡張子から推測します（.geojsonl → geojsonl, .json → osm-overpass,
+    /// それ以外 → shapefile）
+    #[arg(long, value_name = "FORMAT")]
+    format: Option<String>,
 }
 
 // RTree に登録するポリゴン構造体
@@ -26,6 +33,9 @@ struct IndexedPolygon {
     polygon: Polygon<f64>,
     // Shapefile の属性情報（DBF の内容）を保持します
     properties: Value,
+    // ポリゴンの面積。読み込み時に計算しておき、重なる候補の中から
+    // 最も面積の小さい（＝最も詳細な）ポリゴンを選ぶのに使います
+    area: f64,
 }
 
 // RTreeObject の実装。各ポリゴンのバウンディングボックスを返します
@@ -39,10 +49,18 @@ impl RTreeObject for IndexedPolygon {
     }
 }
 
-// 距離計算用の実装（ここでは envelope の距離を使っています）
+// 距離計算用の実装。点がポリゴン内部にあれば 0、そうでなければポリゴンの境界（外輪、
+// 穴の中の点であればその内輪も含む）までの最短距離（の2乗）を返します。単なる envelope（バウンディングボックス）との距離では
+// なく、真の点-ポリゴン距離を使うことで /nearest がポリゴンの外にある点からも
+// 意味のある最近傍を返せるようにしています
 impl PointDistance for IndexedPolygon {
     fn distance_2(&self, point: &[f64; 2]) -> f64 {
-        self.envelope().distance_2(point)
+        let p = Point::new(point[0], point[1]);
+        if self.polygon.contains(&p) {
+            return 0.0;
+        }
+        let distance = self.polygon.euclidean_distance(&p);
+        distance * distance
     }
 }
 
@@ -51,8 +69,58 @@ struct AppState {
     rtree: RTree<IndexedPolygon>,
 }
 
-// GET /query?lat=...&lon=... でクエリされた位置を検索
+// LineString を GeoJSON のリング（座標配列、経度・緯度の順）に変換する
+fn ring_to_geojson_coords(ring: &LineString<f64>) -> Value {
+    json!(ring.coords().map(|c| vec![c.x, c.y]).collect::<Vec<_>>())
+}
+
+// Polygon を GeoJSON の Polygon geometry（外輪 + 内輪）に変換する
+fn polygon_to_geojson_geometry(polygon: &Polygon<f64>) -> Value {
+    let mut rings = vec![ring_to_geojson_coords(polygon.exterior())];
+    rings.extend(polygon.interiors().iter().map(ring_to_geojson_coords));
+    json!({
+        "type": "Polygon",
+        "coordinates": rings,
+    })
+}
+
+// IndexedPolygon を GeoJSON の Feature に変換する
+fn polygon_to_feature(poly: &IndexedPolygon) -> Value {
+    json!({
+        "type": "Feature",
+        "geometry": polygon_to_geojson_geometry(&poly.polygon),
+        "properties": poly.properties,
+    })
+}
+
+// `format=geojson` クエリパラメータ、もしくは Accept: application/geo+json ヘッダを見て
+// GeoJSON 形式のレスポンスが要求されているかを判定する
+fn wants_geojson(req: &HttpRequest, query: &std::collections::HashMap<String, String>) -> bool {
+    if query.get("format").map(String::as_str) == Some("geojson") {
+        return true;
+    }
+    req.headers()
+        .get(ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("application/geo+json"))
+}
+
+// ポリゴンを GeoJSON Feature もしくは生の properties のどちらで返すか決めて変換する
+fn render_polygon(poly: &IndexedPolygon, geojson: bool) -> Value {
+    if geojson {
+        polygon_to_feature(poly)
+    } else {
+        poly.properties.clone()
+    }
+}
+
+// GET /query?lat=...&lon=...[&format=geojson][&resolution=all] でクエリされた位置を検索
+//
+// 行政境界が入れ子になっている場合（都道府県・市区町村・町丁目など）、複数のポリゴンが
+// 同じ点を含むことがあります。デフォルトでは最も面積の小さい（＝最も詳細な）ポリゴンを
+// 返し、`resolution=all` を指定すると面積の昇順（詳細→広域）で全ての一致を返します
 async fn query_polygon(
+    req: HttpRequest,
     data: web::Data<Arc<AppState>>,
     query: web::Query<std::collections::HashMap<String, String>>,
 ) -> impl Responder {
@@ -60,18 +128,119 @@ async fn query_polygon(
     let lat: f64 = query.get("lat").and_then(|s| s.parse().ok()).unwrap_or(0.0);
     let lon: f64 = query.get("lon").and_then(|s| s.parse().ok()).unwrap_or(0.0);
     let point = Point::new(lon, lat);
+    let geojson = wants_geojson(&req, &query);
+    let resolution_all = query.get("resolution").map(String::as_str) == Some("all");
 
-    // RTree でバウンディングボックスに含まれる候補を絞り込む
-    let candidates = data.rtree.locate_all_at_point(&[point.x(), point.y()]);
+    // RTree でバウンディングボックスに含まれる候補を絞り込み、厳密な点内判定を実施
+    let mut matches: Vec<&IndexedPolygon> = data
+        .rtree
+        .locate_all_at_point(&[point.x(), point.y()])
+        .filter(|poly| poly.polygon.contains(&point))
+        .collect();
 
-    // 候補の中から厳密な点内判定を実施
-    for poly in candidates {
-        if poly.polygon.contains(&point) {
-            // 該当するポリゴンがあれば、その属性情報を JSON として返す
-            return HttpResponse::Ok().json(&poly.properties);
+    if matches.is_empty() {
+        return HttpResponse::Ok().json(Value::Null);
+    }
+
+    // 面積の昇順（最も詳細なポリゴンが先頭）にソート
+    matches.sort_by(|a, b| a.area.total_cmp(&b.area));
+
+    if resolution_all {
+        let results: Vec<Value> = matches
+            .into_iter()
+            .map(|poly| render_polygon(poly, geojson))
+            .collect();
+        if geojson {
+            // /query_all と同じ形に揃え、Feature の裸配列ではなく FeatureCollection として返す
+            return HttpResponse::Ok().json(json!({
+                "type": "FeatureCollection",
+                "features": results,
+            }));
         }
+        return HttpResponse::Ok().json(results);
     }
-    HttpResponse::Ok().json(Value::Null)
+
+    // 最も面積の小さいポリゴンを採用する
+    HttpResponse::Ok().json(render_polygon(matches[0], geojson))
+}
+
+// GET /query_all?lat=...&lon=... で、クエリされた位置を含む全てのポリゴンを
+// GeoJSON の FeatureCollection として返す
+async fn query_all(
+    data: web::Data<Arc<AppState>>,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> impl Responder {
+    let lat: f64 = query.get("lat").and_then(|s| s.parse().ok()).unwrap_or(0.0);
+    let lon: f64 = query.get("lon").and_then(|s| s.parse().ok()).unwrap_or(0.0);
+    let point = Point::new(lon, lat);
+
+    let mut matches: Vec<&IndexedPolygon> = data
+        .rtree
+        .locate_all_at_point(&[point.x(), point.y()])
+        .filter(|poly| poly.polygon.contains(&point))
+        .collect();
+    // 面積の昇順（最も詳細なポリゴンが先頭）にソート
+    matches.sort_by(|a, b| a.area.total_cmp(&b.area));
+    let features: Vec<Value> = matches.into_iter().map(polygon_to_feature).collect();
+
+    HttpResponse::Ok().json(json!({
+        "type": "FeatureCollection",
+        "features": features,
+    }))
+}
+
+// GET /nearest?lat=&lon= で、クエリされた位置に最も近い（内部に含む場合は距離0の）
+// ポリゴンの属性情報と、その距離を返す。海岸線やタイルの境目など、どのポリゴンにも
+// 含まれない点に対しても null ではなく最近傍の結果を返せる
+async fn nearest_polygon(
+    data: web::Data<Arc<AppState>>,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> impl Responder {
+    let lat: f64 = query.get("lat").and_then(|s| s.parse().ok()).unwrap_or(0.0);
+    let lon: f64 = query.get("lon").and_then(|s| s.parse().ok()).unwrap_or(0.0);
+    let point = [lon, lat];
+
+    match data.rtree.nearest_neighbor(&point) {
+        Some(poly) => {
+            let distance = poly.distance_2(&point).sqrt();
+            HttpResponse::Ok().json(json!({
+                "properties": poly.properties,
+                "distance": distance,
+            }))
+        }
+        None => HttpResponse::Ok().json(Value::Null),
+    }
+}
+
+// Inner リングを、その最初の頂点を含む Outer リングに割り当てる。複数の Outer が
+// 該当する場合は、最も面積が小さい（＝最も内側にある）ものを選ぶ。戻り値は
+// outer_rings と同じ順序・同じ長さの holes のリスト
+fn assign_holes_to_outers(
+    outer_rings: &[geo::LineString<f64>],
+    inner_rings: Vec<geo::LineString<f64>>,
+) -> Vec<Vec<geo::LineString<f64>>> {
+    let mut holes_by_outer: Vec<Vec<geo::LineString<f64>>> = vec![Vec::new(); outer_rings.len()];
+    for inner in inner_rings {
+        let Some(first) = inner.coords().next() else {
+            continue;
+        };
+        let point = Point::new(first.x, first.y);
+        let best = outer_rings
+            .iter()
+            .enumerate()
+            .filter(|(_, outer)| Polygon::new((*outer).clone(), vec![]).contains(&point))
+            .min_by(|(_, a), (_, b)| {
+                let area_a = Polygon::new((*a).clone(), vec![]).unsigned_area();
+                let area_b = Polygon::new((*b).clone(), vec![]).unsigned_area();
+                area_a.total_cmp(&area_b)
+            });
+        match best {
+            Some((idx, _)) => holes_by_outer[idx].push(inner),
+            // どの Outer リングにも含まれない Inner リングは、壊れたデータとして無視する
+            None => eprintln!("警告: どの Outer リングにも含まれない Inner リングが見つかりました"),
+        }
+    }
+    holes_by_outer
 }
 
 // Shapefile を読み込み、IndexedPolygon のリストを作成する関数
@@ -89,54 +258,43 @@ fn load_polygons_from_shapefile(path: &str) -> Vec<IndexedPolygon> {
         match shape {
             // Polygon の場合の処理
             Shape::Polygon(polygon_shape) => {
-                let mut poly_list = Vec::new();
-                let mut current_polygon: Option<geo::Polygon<f64>> = None;
+                // Shapefile のリング順はパートの並び順に依存し、複数の Outer が
+                // 入り乱れて出てくることがあるため、まず Outer と Inner を
+                // 全て別々に集めてから、後段で Inner を正しい Outer に割り当てる
+                let mut outer_rings: Vec<geo::LineString<f64>> = Vec::new();
+                let mut inner_rings: Vec<geo::LineString<f64>> = Vec::new();
 
-                // shapefile の Polygon は複数のリングを持つことができる
                 for ring in polygon_shape.rings() {
                     match ring {
-                        // Outer リングが出た場合は新しいポリゴンを開始
                         PolygonRing::Outer(points) => {
-                            // すでに現在のポリゴンがあれば確定してリストに追加
-                            if let Some(poly) = current_polygon.take() {
-                                poly_list.push(poly);
-                            }
-                            let exterior_coords = points
+                            let coords = points
                                 .iter()
                                 .map(|pt| geo::Coord { x: pt.x, y: pt.y })
                                 .collect::<Vec<_>>();
-                            // 新しいポリゴンを開始（holes は空）
-                            current_polygon =
-                                Some(geo::Polygon::new(geo::LineString(exterior_coords), vec![]));
+                            outer_rings.push(geo::LineString(coords));
                         }
-                        // Inner リングの場合は、直前の Outer に付与
                         PolygonRing::Inner(points) => {
-                            let interior_coords = points
+                            let coords = points
                                 .iter()
                                 .map(|pt| geo::Coord { x: pt.x, y: pt.y })
                                 .collect::<Vec<_>>();
-                            if let Some(poly) = current_polygon.as_mut() {
-                                let mut interiors = poly.interiors().to_vec();
-                                interiors.push(geo::LineString(interior_coords));
-                                *poly = geo::Polygon::new(poly.exterior().clone(), interiors);
-                            } else {
-                                // Inner リングが最初に来た場合は、どの Outer に属すべきか判断できないので警告を出すか無視する
-                                eprintln!("警告: Outer リングが存在しないのに Inner リングが見つかりました");
-                            }
+                            inner_rings.push(geo::LineString(coords));
                         }
                     }
                 }
-                // ループ後、現在のポリゴンがあれば追加
-                if let Some(poly) = current_polygon.take() {
-                    poly_list.push(poly);
-                }
+
+                let holes_by_outer = assign_holes_to_outers(&outer_rings, inner_rings);
+
                 // 属性情報の処理はそのまま
                 let properties = record_to_json::record_to_json(&record);
-                // マルチポリゴンはPolygonに分割して登録
-                for poly in poly_list {
+                // マルチポリゴンはPolygonに分割して登録（各 Outer はそれぞれ自分の Inner だけを持つ）
+                for (outer, holes) in outer_rings.into_iter().zip(holes_by_outer) {
+                    let poly = geo::Polygon::new(outer, holes);
+                    let area = poly.unsigned_area();
                     polygons.push(IndexedPolygon {
                         polygon: poly,
                         properties: properties.clone(),
+                        area,
                     });
                 }
             }
@@ -148,6 +306,322 @@ fn load_polygons_from_shapefile(path: &str) -> Vec<IndexedPolygon> {
     polygons
 }
 
+// 2点の座標がほぼ同一かどうかを判定する（Overpass のリング連結に使用）
+fn coords_eq(a: geo::Coord<f64>, b: geo::Coord<f64>) -> bool {
+    (a.x - b.x).abs() < 1e-9 && (a.y - b.y).abs() < 1e-9
+}
+
+// LineString が閉じたリング（始点と終点が一致）かどうかを判定する
+fn is_closed_ring(line: &LineString<f64>) -> bool {
+    match (line.0.first(), line.0.last()) {
+        (Some(a), Some(b)) => line.0.len() >= 4 && coords_eq(*a, *b),
+        _ => false,
+    }
+}
+
+// OSM の way セグメント群を、端点が一致するもの同士でつなぎ合わせて閉じたリングにする。
+// way は逆向きで格納されていることもあるため、始点・終点どちらでも一致すれば反転して接続する
+fn join_segments_into_rings(mut segments: Vec<LineString<f64>>) -> Vec<LineString<f64>> {
+    let mut rings = Vec::new();
+    while let Some(mut current) = segments.pop() {
+        while !is_closed_ring(&current) {
+            let tail = match current.0.last() {
+                Some(c) => *c,
+                None => break,
+            };
+            let next_match = segments.iter().enumerate().find_map(|(i, seg)| {
+                let head = *seg.0.first()?;
+                let seg_tail = *seg.0.last()?;
+                if coords_eq(head, tail) {
+                    Some((i, false))
+                } else if coords_eq(seg_tail, tail) {
+                    Some((i, true))
+                } else {
+                    None
+                }
+            });
+            match next_match {
+                Some((i, needs_reverse)) => {
+                    let mut next = segments.remove(i);
+                    if needs_reverse {
+                        next.0.reverse();
+                    }
+                    // 接続点の座標は重複するので取り除いてから繋げる
+                    let mut next_coords = next.0;
+                    if !next_coords.is_empty() {
+                        next_coords.remove(0);
+                    }
+                    current.0.extend(next_coords);
+                }
+                // つながる相手が見つからない場合は、壊れたデータとして諦める
+                None => break,
+            }
+        }
+        rings.push(current);
+    }
+    rings
+}
+
+// Overpass の tags オブジェクトをそのまま properties として使う
+fn tags_to_properties(tags: &Value) -> Value {
+    if tags.is_object() {
+        tags.clone()
+    } else {
+        Value::Object(serde_json::Map::new())
+    }
+}
+
+// Overpass API の `out body; >; out skel;` 形式の JSON を読み込み、
+// IndexedPolygon のリストを作成する関数
+fn load_polygons_from_overpass(path: &str) -> Vec<IndexedPolygon> {
+    let data = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("Overpass JSON の読み込みに失敗しました: {}", e));
+    let json: Value = serde_json::from_str(&data)
+        .unwrap_or_else(|e| panic!("Overpass JSON のパースに失敗しました: {}", e));
+    let elements = json["elements"].as_array().cloned().unwrap_or_default();
+
+    // 1 パス目: node 要素を id -> 座標 の Map に集める
+    let mut nodes: std::collections::HashMap<u64, geo::Coord<f64>> =
+        std::collections::HashMap::new();
+    for el in &elements {
+        if el["type"] == "node" {
+            if let (Some(id), Some(lat), Some(lon)) =
+                (el["id"].as_u64(), el["lat"].as_f64(), el["lon"].as_f64())
+            {
+                nodes.insert(id, geo::Coord { x: lon, y: lat });
+            }
+        }
+    }
+
+    // 2 パス目: way 要素の nodes を座標列に解決し、id -> LineString の Map に集める
+    let mut ways: std::collections::HashMap<u64, LineString<f64>> =
+        std::collections::HashMap::new();
+    for el in &elements {
+        if el["type"] == "way" {
+            if let Some(id) = el["id"].as_u64() {
+                let coords: Vec<geo::Coord<f64>> = el["nodes"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|n| n.as_u64())
+                    .filter_map(|n| nodes.get(&n).copied())
+                    .collect();
+                ways.insert(id, LineString(coords));
+            }
+        }
+    }
+
+    // boundary relation がメンバーとして参照している way の id を先に集めておく。
+    // これらは relation 側で組み立てたタグ付きポリゴンとして登録されるので、
+    // 「閉じた way を単独のポリゴンとして登録する」パスでは二重登録しないようにする
+    let mut boundary_member_way_ids: std::collections::HashSet<u64> = std::collections::HashSet::new();
+    for el in &elements {
+        if el["type"] != "relation" {
+            continue;
+        }
+        let tags = &el["tags"];
+        let is_boundary = tags["boundary"].as_str().is_some() || tags["admin_level"].as_str().is_some();
+        if !is_boundary {
+            continue;
+        }
+        for member in el["members"].as_array().into_iter().flatten() {
+            if member["type"] != "way" {
+                continue;
+            }
+            if let Some(ref_id) = member["ref"].as_u64() {
+                boundary_member_way_ids.insert(ref_id);
+            }
+        }
+    }
+
+    let mut polygons = Vec::new();
+
+    // それ自体が閉じたリングになっている way は、単独のポリゴンとして登録する。
+    // ただし boundary relation のメンバーである way は relation 側で登録されるため除外する
+    for el in &elements {
+        if el["type"] != "way" {
+            continue;
+        }
+        let Some(id) = el["id"].as_u64() else {
+            continue;
+        };
+        if boundary_member_way_ids.contains(&id) {
+            continue;
+        }
+        let Some(line) = ways.get(&id) else {
+            continue;
+        };
+        if !is_closed_ring(line) {
+            continue;
+        }
+        let polygon = Polygon::new(line.clone(), vec![]);
+        let area = polygon.unsigned_area();
+        polygons.push(IndexedPolygon {
+            polygon,
+            properties: tags_to_properties(&el["tags"]),
+            area,
+        });
+    }
+
+    // admin_level / boundary タグを持つ relation は、メンバーの way を
+    // outer/inner に振り分けて繋ぎ合わせ、穴あきポリゴンとして登録する
+    for el in &elements {
+        if el["type"] != "relation" {
+            continue;
+        }
+        let tags = &el["tags"];
+        let is_boundary = tags["boundary"].as_str().is_some() || tags["admin_level"].as_str().is_some();
+        if !is_boundary {
+            continue;
+        }
+
+        let mut outer_segments = Vec::new();
+        let mut inner_segments = Vec::new();
+        for member in el["members"].as_array().into_iter().flatten() {
+            if member["type"] != "way" {
+                continue;
+            }
+            let Some(ref_id) = member["ref"].as_u64() else {
+                continue;
+            };
+            let Some(line) = ways.get(&ref_id) else {
+                continue;
+            };
+            if member["role"].as_str() == Some("inner") {
+                inner_segments.push(line.clone());
+            } else {
+                outer_segments.push(line.clone());
+            }
+        }
+
+        // 参照先の node が解決できない member way はノード数0のセグメントになり、
+        // 閉じたリングに繋がらないことがある。そのようなリングは（GeoJSONL
+        // 側と同じく）Polygon を組み立てる前に捨てる
+        let outer_rings: Vec<LineString<f64>> = join_segments_into_rings(outer_segments)
+            .into_iter()
+            .filter(|ring| {
+                let ok = is_closed_ring(ring);
+                if !ok {
+                    eprintln!("警告: 閉じなかった Outer リングを無視します");
+                }
+                ok
+            })
+            .collect();
+        let inner_rings: Vec<LineString<f64>> = join_segments_into_rings(inner_segments)
+            .into_iter()
+            .filter(|ring| {
+                let ok = is_closed_ring(ring);
+                if !ok {
+                    eprintln!("警告: 閉じなかった Inner リングを無視します");
+                }
+                ok
+            })
+            .collect();
+        let properties = tags_to_properties(tags);
+
+        // 複数の Outer を持つ relation（飛び地など）では、各 Inner を
+        // それが属する Outer にだけ割り当てる（全 Outer に付与しない）
+        let holes_by_outer = assign_holes_to_outers(&outer_rings, inner_rings);
+
+        for (outer, holes) in outer_rings.into_iter().zip(holes_by_outer) {
+            let polygon = Polygon::new(outer, holes);
+            let area = polygon.unsigned_area();
+            polygons.push(IndexedPolygon {
+                polygon,
+                properties: properties.clone(),
+                area,
+            });
+        }
+    }
+
+    polygons
+}
+
+// GeoJSON の 1 つの ring（座標配列）を geo::LineString に変換する
+fn geojson_ring_to_linestring(ring: &Value) -> LineString<f64> {
+    let coords = ring
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|pt| {
+            let pt = pt.as_array()?;
+            let x = pt.first()?.as_f64()?;
+            let y = pt.get(1)?.as_f64()?;
+            Some(geo::Coord { x, y })
+        })
+        .collect::<Vec<_>>();
+    LineString(coords)
+}
+
+// GeoJSON の coordinates（rings の配列。最初が外輪、残りが内輪）を geo::Polygon に変換する。
+// 外輪が空のポリゴンは `bounding_rect()` が `None` になり RTree への登録時に panic するため、
+// 外輪が閉じたリング（4 点以上で始点と終点が一致）でなければここで弾く。閉じていない内輪は捨てる
+fn geojson_rings_to_polygon(rings: &[Value]) -> Option<Polygon<f64>> {
+    let mut rings = rings.iter().map(geojson_ring_to_linestring);
+    let exterior = rings.next()?;
+    if !is_closed_ring(&exterior) {
+        return None;
+    }
+    let interiors: Vec<LineString<f64>> = rings.filter(is_closed_ring).collect();
+    Some(Polygon::new(exterior, interiors))
+}
+
+// GeoJSON の geometry（Polygon または MultiPolygon）を geo::Polygon のリストに変換する。
+// MultiPolygon は Shapefile の読み込みと同じく、個々の Polygon に分割して扱う
+fn geojson_geometry_to_polygons(geometry: &Value) -> Vec<Polygon<f64>> {
+    match geometry.get("type").and_then(Value::as_str) {
+        Some("Polygon") => geometry
+            .get("coordinates")
+            .and_then(Value::as_array)
+            .and_then(|rings| geojson_rings_to_polygon(rings))
+            .into_iter()
+            .collect(),
+        Some("MultiPolygon") => geometry
+            .get("coordinates")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_array)
+            .filter_map(|rings| geojson_rings_to_polygon(rings))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// 改行区切り GeoJSON（GeoJSONL、1 行 1 Feature）を読み込み、IndexedPolygon のリストを作成する。
+// std::io::BufReader で 1 行ずつ読むので、巨大なファイルでも全体を一度にメモリへ展開しない
+fn load_polygons_from_geojsonl(path: &str) -> Vec<IndexedPolygon> {
+    let file = std::fs::File::open(path)
+        .unwrap_or_else(|e| panic!("GeoJSONL の読み込みに失敗しました: {}", e));
+    let reader = std::io::BufReader::new(file);
+
+    let mut polygons = Vec::new();
+    for line in std::io::BufRead::lines(reader) {
+        let line = line.unwrap_or_else(|e| panic!("GeoJSONL の行の読み込みに失敗しました: {}", e));
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let feature: Value = serde_json::from_str(line)
+            .unwrap_or_else(|e| panic!("Feature の JSON パースに失敗しました: {}", e));
+
+        let properties = feature.get("properties").cloned().unwrap_or(Value::Null);
+        let Some(geometry) = feature.get("geometry") else {
+            continue;
+        };
+
+        for polygon in geojson_geometry_to_polygons(geometry) {
+            let area = polygon.unsigned_area();
+            polygons.push(IndexedPolygon {
+                polygon,
+                properties: properties.clone(),
+                area,
+            });
+        }
+    }
+    polygons
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     let args = Args::parse();
@@ -160,8 +634,22 @@ async fn main() -> std::io::Result<()> {
 
     let file_path = args.file.unwrap_or_else(|| "data.shp".to_string());
 
-    // Shapefileからポリゴンを読み込み
-    let polygons = load_polygons_from_shapefile(&file_path);
+    // --format が指定されていればそれに従い、なければ拡張子から読み込み形式を判断する
+    let format = args.format.unwrap_or_else(|| {
+        if file_path.ends_with(".geojsonl") {
+            "geojsonl".to_string()
+        } else if file_path.ends_with(".json") {
+            "osm-overpass".to_string()
+        } else {
+            "shapefile".to_string()
+        }
+    });
+
+    let polygons = match format.as_str() {
+        "osm-overpass" => load_polygons_from_overpass(&file_path),
+        "geojsonl" => load_polygons_from_geojsonl(&file_path),
+        _ => load_polygons_from_shapefile(&file_path),
+    };
     // bulk_load により RTree を一括構築
     let rtree = RTree::bulk_load(polygons);
     let state = Arc::new(AppState { rtree });
@@ -172,8 +660,53 @@ async fn main() -> std::io::Result<()> {
         App::new()
             .app_data(web::Data::new(state.clone()))
             .route("/query", web::get().to(query_polygon))
+            .route("/query_all", web::get().to(query_all))
+            .route("/nearest", web::get().to(nearest_polygon))
     })
     .bind("127.0.0.1:8080")?
     .run()
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 一辺 (max - min) の正方形リングを作るヘルパー
+    fn square(min: f64, max: f64) -> LineString<f64> {
+        LineString(vec![
+            geo::Coord { x: min, y: min },
+            geo::Coord { x: max, y: min },
+            geo::Coord { x: max, y: max },
+            geo::Coord { x: min, y: max },
+            geo::Coord { x: min, y: min },
+        ])
+    }
+
+    #[test]
+    fn donut_hole_is_assigned_to_its_own_multipolygon_member() {
+        // マルチポリゴンのうち、小さな島 A (0..1) と、穴を持つドーナツ状の B (10..20) を用意する
+        let island = square(0.0, 1.0);
+        let donut_outer = square(10.0, 20.0);
+        let donut_hole = square(13.0, 17.0);
+
+        let outer_rings = vec![island.clone(), donut_outer.clone()];
+        let holes_by_outer = assign_holes_to_outers(&outer_rings, vec![donut_hole.clone()]);
+
+        // 穴は島には付かず、ドーナツの Outer にだけ割り当てられる
+        assert!(holes_by_outer[0].is_empty());
+        assert_eq!(holes_by_outer[1], vec![donut_hole.clone()]);
+
+        let island_polygon = Polygon::new(island, holes_by_outer[0].clone());
+        let donut_polygon = Polygon::new(donut_outer, holes_by_outer[1].clone());
+
+        // 穴の中の点は、どのポリゴンにも含まれない
+        let point_in_hole = Point::new(15.0, 15.0);
+        assert!(!donut_polygon.contains(&point_in_hole));
+        assert!(!island_polygon.contains(&point_in_hole));
+
+        // ドーナツの実体（穴ではない部分）の点は含まれる
+        let point_in_ring = Point::new(10.5, 10.5);
+        assert!(donut_polygon.contains(&point_in_ring));
+    }
+}
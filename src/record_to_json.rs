@@ -1,9 +1,14 @@
 use serde_json::json;
 use serde_json::Value;
-use shapefile::dbase::FieldValue;
+use shapefile::dbase::{Date, FieldValue};
 use shapefile::dbase::Record;
 
-// 仮の FieldValue の変換関数
+// DBF の Date を ISO 8601 (YYYY-MM-DD) 形式の文字列に変換する
+fn date_to_iso_string(date: &Date) -> String {
+    format!("{:04}-{:02}-{:02}", date.year(), date.month(), date.day())
+}
+
+// FieldValue の変換関数。DBF のフィールド型を対応する JSON 値に変換する（DateTime など未対応の型は null）
 pub fn field_value_to_json(value: &FieldValue) -> Value {
     match value {
         FieldValue::Character(opt) => {
@@ -13,7 +18,15 @@ pub fn field_value_to_json(value: &FieldValue) -> Value {
                 Value::Null
             }
         }
-        // 他の型の場合もここに実装する
+        FieldValue::Numeric(opt) => opt.map_or(Value::Null, |n| json!(n)),
+        FieldValue::Float(opt) => opt.map_or(Value::Null, |n| json!(n)),
+        FieldValue::Integer(n) => json!(n),
+        FieldValue::Logical(opt) => opt.map_or(Value::Null, |b| json!(b)),
+        FieldValue::Date(opt) => opt.as_ref().map_or(Value::Null, |d| json!(date_to_iso_string(d))),
+        FieldValue::Currency(n) => json!(n),
+        FieldValue::Double(n) => json!(n),
+        FieldValue::Memo(s) => json!(s),
+        // DateTime など、その他の型は未対応のため null を返す
         _ => Value::Null,
     }
 }